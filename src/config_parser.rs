@@ -6,8 +6,10 @@
 
 extern crate xml;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
 use xml::attribute::OwnedAttribute;
 use xml::reader::{EventReader, XmlEvent};
 
@@ -98,33 +100,244 @@ impl FontFamily {
 
 impl PartialEq for FontFamily {
     fn eq(&self, other: &Self) -> bool {
-        (self.lang == other.lang && self.name.is_none() && other.name.is_none())
+        (self.lang == other.lang
+            && self.variant == other.variant
+            && self.name.is_none()
+            && other.name.is_none())
             || (self.name.is_some() && other.name.is_some() && self.name == other.name)
     }
 }
 
+/// A single variable-font axis setting, e.g. `wght` at `700.0`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontVariation {
+    pub tag: String,
+    pub value: f64,
+}
+
+/// A CSS-style generic font family, independent of any concrete family
+/// name a particular `fonts.xml` happens to define.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenericFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+    SystemUi,
+}
+
+impl GenericFamily {
+    fn default_name(&self) -> &'static str {
+        match self {
+            GenericFamily::Serif => "serif",
+            GenericFamily::SansSerif => "sans-serif",
+            GenericFamily::Monospace => "monospace",
+            GenericFamily::Cursive => "cursive",
+            GenericFamily::Fantasy => "fantasy",
+            GenericFamily::SystemUi => "system-ui",
+        }
+    }
+}
+
 pub struct AndroidFontConfig {
     font_families: Vec<FontFamily>,
     font_aliases: Vec<FontAlias>,
+    // (lang, font) pairs for every nameless fallback family, precomputed
+    // once so `fallback_chain` doesn't re-scan `font_families` per call.
+    fallback_entries: Vec<(String, FontEntry)>,
+    // Indexes into `font_families`/`font_aliases`, built once at
+    // construction so the selectors below look families up by name or
+    // lang, and aliases by name, instead of scanning every family or alias
+    // on each lookup; nameless fallback families are still walked through
+    // `nameless_families`.
+    family_name_index: HashMap<String, Vec<usize>>,
+    family_lang_index: HashMap<String, Vec<usize>>,
+    nameless_families: Vec<usize>,
+    alias_index: HashMap<String, Vec<usize>>,
 }
 
 #[allow(dead_code)]
 impl AndroidFontConfig {
     pub fn new() -> AndroidFontConfig {
-        let (families, aliases) = AndroidFontConfig::parse("/etc/fonts.xml");
-        AndroidFontConfig {
-            font_families: families,
-            font_aliases: aliases,
-        }
+        let paths = AndroidFontConfig::standard_config_paths();
+        let paths: Vec<&str> = paths.iter().map(|p| p.as_str()).collect();
+        AndroidFontConfig::from_paths(&paths)
     }
 
     #[cfg(test)]
     pub fn new_from_file(config_xml: &str) -> AndroidFontConfig {
-        let (families, aliases) = AndroidFontConfig::parse(config_xml);
+        AndroidFontConfig::from_paths(&[config_xml])
+    }
+
+    /// Parse and merge every config in `config_xml_paths`, in order.
+    ///
+    /// Families with the same name (or, for nameless fallback families, the
+    /// same `lang` and `variant`) are unioned by appending their
+    /// `FontEntry`s. A later
+    /// file's entry overrides an earlier one for the same (weight, italic,
+    /// fallbackFor) key within that family, which lets a vendor override
+    /// file take precedence over the system default it was layered on top
+    /// of. Missing paths are skipped, since not every device ships every
+    /// file this crate knows how to probe for.
+    pub fn from_paths(config_xml_paths: &[&str]) -> AndroidFontConfig {
+        let mut font_families: Vec<FontFamily> = Vec::new();
+        let mut font_aliases: Vec<FontAlias> = Vec::new();
+
+        for path in config_xml_paths {
+            if !Path::new(path).exists() {
+                continue;
+            }
+            let (families, aliases) = AndroidFontConfig::parse(path);
+            for family in families {
+                AndroidFontConfig::merge_family(&mut font_families, family);
+            }
+            font_aliases.extend(aliases);
+        }
+
+        let fallback_entries = AndroidFontConfig::build_fallback_entries(&font_families);
+        let family_name_index = AndroidFontConfig::build_family_name_index(&font_families);
+        let family_lang_index = AndroidFontConfig::build_family_lang_index(&font_families);
+        let nameless_families = (0..font_families.len())
+            .filter(|&i| font_families[i].name.is_none())
+            .collect();
+        let alias_index = AndroidFontConfig::build_alias_index(&font_aliases);
+
         AndroidFontConfig {
-            font_families: families,
-            font_aliases: aliases,
+            font_families,
+            font_aliases,
+            fallback_entries,
+            family_name_index,
+            family_lang_index,
+            nameless_families,
+            alias_index,
+        }
+    }
+
+    /// Map family name -> indices into `font_families`.
+    fn build_family_name_index(families: &[FontFamily]) -> HashMap<String, Vec<usize>> {
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, family) in families.iter().enumerate() {
+            if let Some(name) = &family.name {
+                index.entry(name.clone()).or_default().push(i);
+            }
+        }
+        index
+    }
+
+    /// Map language tag (`""` for families with no `lang`) -> indices into
+    /// `font_families`.
+    fn build_family_lang_index(families: &[FontFamily]) -> HashMap<String, Vec<usize>> {
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, family) in families.iter().enumerate() {
+            let lang = family.lang.clone().unwrap_or_default();
+            index.entry(lang).or_default().push(i);
         }
+        index
+    }
+
+    /// Map alias name -> indices into `font_aliases`.
+    fn build_alias_index(aliases: &[FontAlias]) -> HashMap<String, Vec<usize>> {
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, alias) in aliases.iter().enumerate() {
+            index
+                .entry(alias.name.clone())
+                .or_default()
+                .push(i);
+        }
+        index
+    }
+
+    /// Merge `incoming` into `font_families`, unioning its fonts into any
+    /// existing family with the same name (or, if nameless, the same lang
+    /// and variant).
+    fn merge_family(font_families: &mut Vec<FontFamily>, incoming: FontFamily) {
+        if let Some(existing) = font_families.iter_mut().find(|f| **f == incoming) {
+            for font in incoming.fonts {
+                let slot = existing.fonts.iter_mut().find(|e| {
+                    e.weight == font.weight
+                        && e.italic == font.italic
+                        && e.fallback_for == font.fallback_for
+                });
+                match slot {
+                    Some(slot) => *slot = font,
+                    None => existing.fonts.push(font),
+                }
+            }
+        } else {
+            font_families.push(incoming);
+        }
+    }
+
+    /// Flatten every nameless, language-tagged fallback family into
+    /// `(lang, font)` pairs, in file order, for `fallback_chain` to sort.
+    fn build_fallback_entries(font_families: &[FontFamily]) -> Vec<(String, FontEntry)> {
+        let mut entries = vec![];
+        for family in font_families {
+            if family.name.is_some() {
+                continue;
+            }
+            if let Some(lang) = &family.lang {
+                for font in &family.fonts {
+                    if font.is_regular() && font.path.is_some() {
+                        entries.push((lang.clone(), font.clone()));
+                    }
+                }
+            }
+        }
+        entries
+    }
+
+    /// ISO 15924 script tag for a handful of languages whose Android
+    /// fallback family is keyed by script (`und-<Script>`) rather than by
+    /// language, e.g. Thai text falls back to the family tagged
+    /// `und-Thai`, not `th`.
+    fn script_for_lang(lang: &str) -> Option<&'static str> {
+        match lang {
+            "th" => Some("Thai"),
+            "km" => Some("Khmr"),
+            "ka" => Some("Geor"),
+            "he" | "iw" => Some("Hebr"),
+            "ar" => Some("Arab"),
+            "hi" => Some("Deva"),
+            "bn" => Some("Beng"),
+            "ta" => Some("Taml"),
+            "te" => Some("Telu"),
+            "kn" => Some("Knda"),
+            "ml" => Some("Mlym"),
+            "si" => Some("Sinh"),
+            "my" => Some("Mymr"),
+            "lo" => Some("Laoo"),
+            "bo" => Some("Tibt"),
+            "am" | "ti" => Some("Ethi"),
+            _ => None,
+        }
+    }
+
+    /// Standard locations Android composes its font set from: the system
+    /// `fonts.xml`, a vendor override of the same name, and any
+    /// `fallback_fonts-XX.xml` locale overrides a vendor image ships under
+    /// `/vendor/etc`.
+    fn standard_config_paths() -> Vec<String> {
+        let mut paths = vec!["/etc/fonts.xml".to_string(), "/vendor/etc/fonts.xml".to_string()];
+
+        if let Ok(entries) = std::fs::read_dir("/vendor/etc") {
+            let mut vendor_fallbacks: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.starts_with("fallback_fonts") && name.ends_with(".xml"))
+                        .unwrap_or(false)
+                })
+                .filter_map(|path| path.to_str().map(|s| s.to_string()))
+                .collect();
+            vendor_fallbacks.sort();
+            paths.append(&mut vendor_fallbacks);
+        }
+
+        paths
     }
 
     fn parse_alias(attributes: &Vec<OwnedAttribute>) -> FontAlias {
@@ -215,6 +428,12 @@ impl AndroidFontConfig {
         axis
     }
 
+    /// Parse a `fonts.xml` config, understanding both the modern LMP+ schema
+    /// (`<family name="...">` with inline `<font>` entries) and the legacy
+    /// pre-Lollipop `system_fonts.xml`/`fallback_fonts.xml` schema (a
+    /// `<nameset>` of `<name>`s and a `<fileset>` of `<file>`s). The two
+    /// schemas never mix within a single `<family>`, so both are handled in
+    /// the same streaming pass by keying off which child elements show up.
     fn parse(config_xml_path: &str) -> (Vec<FontFamily>, Vec<FontAlias>) {
         let file = BufReader::new(File::open(config_xml_path).unwrap());
         let parser = EventReader::new(file);
@@ -225,6 +444,8 @@ impl AndroidFontConfig {
 
         let mut font = FontEntry::new();
         let mut family = FontFamily::new();
+        let mut nameset_names: Vec<String> = Vec::new();
+        let mut fileset_index: i32 = 0;
 
         for e in parser {
             match e {
@@ -239,12 +460,33 @@ impl AndroidFontConfig {
 
                         "family" => {
                             family = AndroidFontConfig::parse_family(&attributes);
+                            nameset_names.clear();
                         }
 
                         "font" => {
                             font = AndroidFontConfig::parse_font(&attributes);
                         }
 
+                        "fileset" => {
+                            fileset_index = 0;
+                        }
+
+                        "file" => {
+                            font = FontEntry::new();
+                            // Legacy fileset entries carry no weight/style
+                            // attributes; AOSP's own parser infers them from
+                            // position: regular, bold, italic, bold-italic.
+                            let (weight, italic) = match fileset_index {
+                                0 => (400, false),
+                                1 => (700, false),
+                                2 => (400, true),
+                                _ => (700, true),
+                            };
+                            font.weight = Some(weight);
+                            font.italic = italic;
+                            fileset_index += 1;
+                        }
+
                         "axis" => {
                             font.axis.push(AndroidFontConfig::parse_axis(&attributes));
                         }
@@ -261,7 +503,29 @@ impl AndroidFontConfig {
                                 family.fonts.push(font.clone());
                             }
                         }
+                        "file" => {
+                            if font.path.is_some() && current_elements.last().unwrap() == "fileset"
+                            {
+                                family.fonts.push(font.clone());
+                            }
+                        }
                         "family" => {
+                            // Legacy schema: the first <name> becomes the
+                            // canonical family name, the rest become
+                            // implicit aliases pointing to it.
+                            if !nameset_names.is_empty() {
+                                let canonical = nameset_names[0].clone();
+                                for alias_name in &nameset_names[1..] {
+                                    font_aliases.push(FontAlias {
+                                        name: alias_name.clone(),
+                                        to: canonical.clone(),
+                                        weight: None,
+                                    });
+                                }
+                                family.name = Some(canonical);
+                                nameset_names.clear();
+                            }
+
                             if let Some(family_lang) = &family.lang {
                                 let lang_attr = family_lang.clone();
                                 if lang_attr.contains(",") {
@@ -289,8 +553,14 @@ impl AndroidFontConfig {
                     }
                 }
                 Ok(XmlEvent::Characters(s)) => {
-                    if current_elements.last().unwrap() == "font" {
-                        font.path = Some("/system/fonts/".to_owned() + &s.trim());
+                    match current_elements.last().map(|e| e.as_str()) {
+                        Some("font") | Some("file") => {
+                            font.path = Some("/system/fonts/".to_owned() + &s.trim());
+                        }
+                        Some("name") => {
+                            nameset_names.push(s.trim().to_string());
+                        }
+                        _ => {}
                     }
                 }
                 _ => {}
@@ -311,12 +581,13 @@ impl AndroidFontConfig {
 
     /// Return font family by resolving alias name
     fn resolve_font_family_by_alias<'a>(&'a self, name: &'a str) -> &'a str {
-        for alias in &self.font_aliases {
-            if alias.name == name {
-                return &alias.to;
-            }
+        match self.alias_index.get(name) {
+            Some(indices) => indices
+                .first()
+                .map(|&i| self.font_aliases[i].to.as_str())
+                .unwrap_or(name),
+            None => name,
         }
-        name
     }
 
     /// Return font path by font family and language
@@ -325,8 +596,16 @@ impl AndroidFontConfig {
         name: &str,
         lang: &str,
     ) -> Result<(&str, i32), String> {
-        for family in &self.font_families {
-            if family.lang.is_some() && family.lang.as_ref().unwrap() == lang {
+        if let Some(indices) = self.family_lang_index.get(lang) {
+            for &i in indices {
+                let family = &self.font_families[i];
+                // The index buckets families with no `lang` attribute under
+                // "" alongside any family whose `lang` is literally "", but
+                // this lookup should only ever consider families that
+                // actually carry the requested lang.
+                if family.lang.is_none() {
+                    continue;
+                }
                 for font in &family.fonts {
                     if let Some(fallback) = &font.fallback_for {
                         if fallback == name {
@@ -343,17 +622,9 @@ impl AndroidFontConfig {
 
     /// Return font path of default font by language
     pub fn default_font_path_by_lang(&self, lang: &str) -> Result<(&str, i32), &'static str> {
-        for family in &self.font_families {
-            if let Some(font_lang) = &family.lang {
-                if font_lang == lang {
-                    for font in &family.fonts {
-                        if font.is_regular() && font.path.is_some() {
-                            return Ok((font.path.as_ref().unwrap(), font.index));
-                        }
-                    }
-                }
-            } else if lang.is_empty() {
-                for font in &family.fonts {
+        if let Some(indices) = self.family_lang_index.get(lang) {
+            for &i in indices {
+                for font in &self.font_families[i].fonts {
                     if font.is_regular() && font.path.is_some() {
                         return Ok((font.path.as_ref().unwrap(), font.index));
                     }
@@ -363,6 +634,52 @@ impl AndroidFontConfig {
         Err("not found")
     }
 
+    /// Return the ordered fallback chain for `lang`: the default font for
+    /// `lang` first (if any), then every language-tagged fallback family,
+    /// sorted so an exact `lang` match comes first, a script match (e.g.
+    /// `und-Thai` for `th`) comes next, and the remaining CJK/pan-Unicode
+    /// fallbacks come last. Built from the `fallback_entries` precomputed
+    /// at construction, so this never rescans `font_families`.
+    pub fn fallback_chain(&self, lang: &str) -> Vec<(String, i32)> {
+        let mut chain: Vec<(String, i32)> = vec![];
+        let default = self
+            .default_font_path_by_lang(lang)
+            .ok()
+            .map(|(path, index)| (path.to_string(), index));
+        if let Some(entry) = &default {
+            chain.push(entry.clone());
+        }
+
+        let script_tag = AndroidFontConfig::script_for_lang(lang).map(|s| format!("und-{}", s));
+        let mut exact = vec![];
+        let mut script_matched = vec![];
+        let mut rest = vec![];
+
+        for (family_lang, font) in &self.fallback_entries {
+            if family_lang == lang {
+                exact.push(font);
+            } else if Some(family_lang.as_str()) == script_tag.as_deref() {
+                script_matched.push(font);
+            } else {
+                rest.push(font);
+            }
+        }
+
+        for font in exact.into_iter().chain(script_matched).chain(rest) {
+            let entry = (font.path.clone().unwrap(), font.index);
+            // The same (path, index) can show up more than once -- e.g. a
+            // comma-separated `lang="zh-Hans,zh-Hant"` family is split into
+            // one clone per lang by `parse()`, each carrying the same
+            // fonts, and one of those clones may also be `default` above.
+            // Don't list the same font twice.
+            if chain.contains(&entry) {
+                continue;
+            }
+            chain.push(entry);
+        }
+        chain
+    }
+
     /// Return all font paths.
     pub fn all_font_paths(&self) -> Vec<(String, i32)> {
         self.font_families
@@ -395,32 +712,296 @@ impl AndroidFontConfig {
     ) -> Result<Vec<(String, i32)>, &'static str> {
         let family_name = self.resolve_font_family_by_alias(family_name);
         let mut paths: Vec<(String, i32)> = vec![];
-        for family in &self.font_families {
-            if let Some(name) = &family.name {
-                if family_name == name {
-                    family
-                        .fonts
-                        .iter()
-                        .filter(|font| font.path.is_some() && font.is_regular())
-                        .for_each(|font| paths.push((font.path.clone().unwrap(), font.index)));
-                }
-            } else {
-                family
+
+        if let Some(indices) = self.family_name_index.get(family_name) {
+            for &i in indices {
+                self.font_families[i]
                     .fonts
                     .iter()
-                    .filter(|font| {
-                        (font.fallback_for.is_some()
-                            && font.fallback_for.as_ref().unwrap() == family_name)
-                            || (font.fallback_for.is_none() && family_name == "sans-serif")
-                    })
+                    .filter(|font| font.path.is_some() && font.is_regular())
                     .for_each(|font| paths.push((font.path.clone().unwrap(), font.index)));
             }
         }
+        for &i in &self.nameless_families {
+            self.font_families[i]
+                .fonts
+                .iter()
+                .filter(|font| {
+                    (font.fallback_for.is_some()
+                        && font.fallback_for.as_ref().unwrap() == family_name)
+                        || (font.fallback_for.is_none() && family_name == "sans-serif")
+                })
+                .for_each(|font| paths.push((font.path.clone().unwrap(), font.index)));
+        }
+
         if paths.len() > 0 {
             return Ok(paths);
         }
         Err("not found")
     }
+
+    /// Resolve a CSS-style generic family (`serif`, `sans-serif`, ...) to
+    /// the concrete fonts for `lang`: the literally-named family (if any)
+    /// plus any nameless fallback family whose own `lang` matches `lang`,
+    /// exactly or by script (e.g. `und-Thai` for `th`), same approach
+    /// `fallback_chain` uses. A fallback family for a different language
+    /// must never leak into the result.
+    pub fn resolve_generic(&self, generic: GenericFamily, lang: &str) -> Vec<(String, i32)> {
+        let family_name = generic.default_name();
+        let mut paths: Vec<(String, i32)> = vec![];
+
+        // A literal `<family name="serif">` (or similar) has no notion of
+        // language and always applies.
+        if let Some(indices) = self.family_name_index.get(family_name) {
+            for &i in indices {
+                self.font_families[i]
+                    .fonts
+                    .iter()
+                    .filter(|font| font.path.is_some() && font.is_regular())
+                    .for_each(|font| paths.push((font.path.clone().unwrap(), font.index)));
+            }
+        }
+
+        // Nameless, `lang`-tagged fallback families only override the
+        // generic when their `lang` actually matches the request: exact
+        // match first, then a script match (e.g. `und-Thai` for `th`).
+        let script_tag = AndroidFontConfig::script_for_lang(lang).map(|s| format!("und-{}", s));
+        let mut exact = vec![];
+        let mut script_matched = vec![];
+
+        for &i in &self.nameless_families {
+            let family = &self.font_families[i];
+            let family_lang = match &family.lang {
+                Some(l) => l,
+                None => continue,
+            };
+            let is_exact = family_lang == lang;
+            let is_script = Some(family_lang.as_str()) == script_tag.as_deref();
+            if !is_exact && !is_script {
+                continue;
+            }
+            for font in &family.fonts {
+                if font.path.is_none() {
+                    continue;
+                }
+                let matches_generic = (font.fallback_for.is_some()
+                    && font.fallback_for.as_ref().unwrap() == family_name)
+                    || (font.fallback_for.is_none() && family_name == "sans-serif");
+                if !matches_generic {
+                    continue;
+                }
+                if is_exact {
+                    exact.push(font);
+                } else {
+                    script_matched.push(font);
+                }
+            }
+        }
+
+        paths.extend(
+            exact
+                .into_iter()
+                .chain(script_matched)
+                .map(|font| (font.path.clone().unwrap(), font.index)),
+        );
+        paths
+    }
+
+    /// Return every `FontEntry` belonging to `family_name`, ignoring style.
+    fn candidates_for_family<'a>(&'a self, family_name: &'a str) -> Vec<&'a FontEntry> {
+        let mut candidates: Vec<&FontEntry> = vec![];
+
+        if let Some(indices) = self.family_name_index.get(family_name) {
+            for &i in indices {
+                candidates.extend(
+                    self.font_families[i]
+                        .fonts
+                        .iter()
+                        .filter(|font| font.path.is_some()),
+                );
+            }
+        }
+        for &i in &self.nameless_families {
+            candidates.extend(self.font_families[i].fonts.iter().filter(|font| {
+                font.path.is_some()
+                    && ((font.fallback_for.is_some()
+                        && font.fallback_for.as_ref().unwrap() == family_name)
+                        || (font.fallback_for.is_none() && family_name == "sans-serif"))
+            }));
+        }
+        candidates
+    }
+
+    /// Pick the entry in `candidates` whose weight is the closest match for
+    /// `desired`, using the CSS nearest-weight fallback rule: within
+    /// 400..=500 search upward to 500 first, then downward, then above 500;
+    /// below 400 search downward first, then upward; above 500 search
+    /// upward first, then downward.
+    fn nearest_weight<'a>(
+        candidates: &[&'a FontEntry],
+        desired: i32,
+    ) -> Result<&'a FontEntry, &'static str> {
+        let weight_of = |font: &FontEntry| font.weight.unwrap_or(400);
+
+        let mut ordered: Vec<&FontEntry> = vec![];
+        if (400..=500).contains(&desired) {
+            let mut up: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) >= desired && weight_of(f) <= 500)
+                .collect();
+            up.sort_by_key(|f| weight_of(f));
+            let mut down: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) < desired)
+                .collect();
+            down.sort_by_key(|f| std::cmp::Reverse(weight_of(f)));
+            let mut above: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) > 500)
+                .collect();
+            above.sort_by_key(|f| weight_of(f));
+            ordered.append(&mut up);
+            ordered.append(&mut down);
+            ordered.append(&mut above);
+        } else if desired < 400 {
+            let mut le: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) <= desired)
+                .collect();
+            le.sort_by_key(|f| std::cmp::Reverse(weight_of(f)));
+            let mut gt: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) > desired)
+                .collect();
+            gt.sort_by_key(|f| weight_of(f));
+            ordered.append(&mut le);
+            ordered.append(&mut gt);
+        } else {
+            let mut ge: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) >= desired)
+                .collect();
+            ge.sort_by_key(|f| weight_of(f));
+            let mut lt: Vec<&FontEntry> = candidates
+                .iter()
+                .cloned()
+                .filter(|f| weight_of(f) < desired)
+                .collect();
+            lt.sort_by_key(|f| std::cmp::Reverse(weight_of(f)));
+            ordered.append(&mut ge);
+            ordered.append(&mut lt);
+        }
+
+        ordered.into_iter().next().ok_or("not found")
+    }
+
+    /// Return the font for `family` that best matches `weight` and `italic`,
+    /// using the CSS font-matching algorithm: candidates are first narrowed
+    /// to the requested italic flag (falling back to the other style if the
+    /// family has none), then the nearest weight wins.
+    pub fn select_font(
+        &self,
+        family: &str,
+        weight: i32,
+        italic: bool,
+    ) -> Result<(String, i32), &'static str> {
+        let pool = self.style_pool(family, italic)?;
+        let font = AndroidFontConfig::nearest_weight(&pool, weight)?;
+        Ok((font.path.clone().unwrap(), font.index))
+    }
+
+    /// Candidates for `family`, narrowed to the requested italic flag when
+    /// the family has at least one entry of that style, else every style.
+    fn style_pool<'a>(
+        &'a self,
+        family: &'a str,
+        italic: bool,
+    ) -> Result<Vec<&'a FontEntry>, &'static str> {
+        let family = self.resolve_font_family_by_alias(family);
+        let candidates = self.candidates_for_family(family);
+        if candidates.is_empty() {
+            return Err("not found");
+        }
+
+        let style_matched: Vec<&FontEntry> = candidates
+            .iter()
+            .cloned()
+            .filter(|font| font.italic == italic)
+            .collect();
+        if !style_matched.is_empty() {
+            Ok(style_matched)
+        } else {
+            Ok(candidates)
+        }
+    }
+
+    /// Return the variation-font axis settings needed to render `family` at
+    /// `weight`/`italic`. If a `FontEntry` with exactly that weight exists,
+    /// its own `axis` list (if any) is returned as-is. Otherwise, if the
+    /// nearest-weight entry carries a `wght` axis, its axes are returned with
+    /// `wght` set to the requested weight, clamped to the range of `wght`
+    /// values declared across the matching entries, so callers can drive a
+    /// variable font directly; if it carries no `wght` axis, the list is empty.
+    pub fn select_variation(&self, family: &str, weight: i32, italic: bool) -> Vec<FontVariation> {
+        let pool = match self.style_pool(family, italic) {
+            Ok(pool) => pool,
+            Err(_) => return vec![],
+        };
+
+        if let Some(exact) = pool
+            .iter()
+            .find(|font| font.weight.unwrap_or(400) == weight)
+        {
+            return exact
+                .axis
+                .iter()
+                .map(|axis| FontVariation {
+                    tag: axis.tag.clone(),
+                    value: axis.stylevalue,
+                })
+                .collect();
+        }
+
+        let nearest = match AndroidFontConfig::nearest_weight(&pool, weight) {
+            Ok(font) => font,
+            Err(_) => return vec![],
+        };
+        if !nearest.axis.iter().any(|axis| axis.tag == "wght") {
+            return vec![];
+        }
+
+        // Clamp to the wght range the family actually declares across its
+        // variation instances, so a request outside that range (e.g. 1000
+        // against a 100..900 axis) doesn't ask the renderer for an
+        // out-of-gamut instance.
+        let wght_instances = pool
+            .iter()
+            .flat_map(|font| &font.axis)
+            .filter(|axis| axis.tag == "wght")
+            .map(|axis| axis.stylevalue);
+        let min_wght = wght_instances.clone().fold(f64::INFINITY, f64::min);
+        let max_wght = wght_instances.fold(f64::NEG_INFINITY, f64::max);
+        let clamped_weight = (weight as f64).max(min_wght).min(max_wght);
+
+        nearest
+            .axis
+            .iter()
+            .map(|axis| FontVariation {
+                tag: axis.tag.clone(),
+                value: if axis.tag == "wght" {
+                    clamped_weight
+                } else {
+                    axis.stylevalue
+                },
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -551,6 +1132,135 @@ fn test_all_font_paths() {
         .contains(&("/system/fonts/NotoSansCJK-Regular.ttc".to_owned(), 2)));
 }
 
+#[cfg(test)]
+#[test]
+fn test_legacy_schema() {
+    let config = AndroidFontConfig::new_from_file("data/system_fonts-1.xml");
+    assert_eq!(config.default_family_name(), "sans-serif");
+    assert_eq!(
+        config.default_font_path_by_lang("").unwrap(),
+        ("/system/fonts/Roboto-Regular.ttf", 0)
+    );
+    // The nameset's second <name> becomes an implicit alias.
+    assert!(config
+        .select_family_by_name("sans-serif-light")
+        .unwrap()
+        .contains(&("/system/fonts/Roboto-Regular.ttf".to_owned(), 0)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_legacy_fallback_fonts() {
+    let config = AndroidFontConfig::new_from_file("data/fallback_fonts-1.xml");
+    assert_eq!(
+        config.default_font_path_by_lang("ja").unwrap(),
+        ("/system/fonts/NotoSansCJK-Regular.ttc", 0)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_from_paths_merges_vendor_override() {
+    // The vendor file adds a new weight to "sans-serif" and appends a
+    // brand-new language fallback after the system file's own fallbacks.
+    let config = AndroidFontConfig::from_paths(&["data/fonts-1.xml", "data/vendor-fonts-1.xml"]);
+    assert!(config
+        .select_family_by_name("sans-serif")
+        .unwrap()
+        .contains(&("/system/fonts/Roboto-Black.ttf".to_owned(), 0)));
+    assert_eq!(
+        config.default_font_path_by_lang("km").unwrap(),
+        ("/system/fonts/NotoSansKhmer-Regular.ttf", 0)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_select_font_weight_and_style() {
+    let config = AndroidFontConfig::new_from_file("data/fonts-1.xml");
+    // Exact weight/style match.
+    assert_eq!(
+        config.select_font("sans-serif", 400, false).unwrap(),
+        ("/system/fonts/Roboto-Regular.ttf".to_owned(), 0)
+    );
+    // Nearest weight above 500 searches upward first.
+    assert_eq!(
+        config.select_font("sans-serif", 700, false).unwrap().0,
+        "/system/fonts/Roboto-Bold.ttf".to_owned()
+    );
+    // No italic Thin exists, so this should still resolve via nearest
+    // weight (within the italic entries if the family has any, else within
+    // the non-italic ones).
+    assert!(config.select_font("sans-serif", 100, true).is_ok());
+}
+
+#[cfg(test)]
+#[test]
+fn test_select_variation_synthesizes_wght() {
+    let config = AndroidFontConfig::new_from_file("data/fonts-1.xml");
+    // und-Khmr is backed by a variable font with a wght axis and no
+    // discrete 700-weight <font> entry, so the axis should be synthesized.
+    let variations = config.select_variation("NotoSansKhmer-VF", 700, false);
+    assert!(variations.contains(&FontVariation {
+        tag: "wght".to_owned(),
+        value: 700.0,
+    }));
+}
+
+#[cfg(test)]
+#[test]
+fn test_fallback_chain_orders_exact_then_script_then_rest() {
+    let config = AndroidFontConfig::new_from_file("data/fonts-1.xml");
+    let chain = config.fallback_chain("th");
+
+    // The `und-Thai` script fallback for "th" should outrank the trailing
+    // CJK/pan-Unicode fallbacks.
+    let thai_pos = chain
+        .iter()
+        .position(|(path, _)| path == "/system/fonts/NotoSansThai-Regular.ttf")
+        .unwrap();
+    let cjk_pos = chain
+        .iter()
+        .position(|(path, _)| path == "/system/fonts/NotoSansCJK-Regular.ttc")
+        .unwrap();
+    assert!(thai_pos < cjk_pos);
+}
+
+#[cfg(test)]
+#[test]
+fn test_fallback_chain_dedups_comma_split_lang_family() {
+    // A `lang="zh-Hans,zh-Hant"` family is split by `parse()` into one
+    // clone per lang, each carrying the same fonts -- the chain for either
+    // lang should only list each (path, index) once.
+    let config = AndroidFontConfig::new_from_file("data/fonts-1.xml");
+    let chain = config.fallback_chain("zh-Hans");
+    let cjk_count = chain
+        .iter()
+        .filter(|(path, index)| path == "/system/fonts/NotoSerifCJK-Regular.ttc" && *index == 2)
+        .count();
+    assert!(cjk_count <= 1);
+}
+
+#[cfg(test)]
+#[test]
+fn test_resolve_generic_per_language() {
+    let config = AndroidFontConfig::new_from_file("data/fonts-1.xml");
+    // "en" has no lang-specific override, so only the global "serif"
+    // family should come back -- not every other language's fallback face.
+    assert_eq!(
+        config.resolve_generic(GenericFamily::Serif, "en"),
+        vec![("/system/fonts/NotoSerif-Regular.ttf".to_owned(), 0)]
+    );
+    // "zh-Hans" has a lang-matching CJK serif fallback, which should be
+    // included alongside the global default.
+    assert!(config
+        .resolve_generic(GenericFamily::Serif, "zh-Hans")
+        .contains(&("/system/fonts/NotoSerifCJK-Regular.ttc".to_owned(), 2)));
+    assert!(!config
+        .resolve_generic(GenericFamily::Serif, "en")
+        .contains(&("/system/fonts/NotoSerifThai-Regular.ttf".to_owned(), 0)));
+}
+
 #[cfg(test)]
 #[test]
 fn test_all_families() {